@@ -0,0 +1,152 @@
+use std::fmt;
+use std::str::FromStr;
+
+use clap::ArgMatches;
+use reqwest::Client;
+
+use auth::Authenticate;
+use config::Config;
+use error::{Error, Result};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CampaignId(pub i64);
+
+impl FromStr for CampaignId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.parse().map(CampaignId).map_err(|_| Error::Command(format!("invalid campaign id: {}", s)))
+    }
+}
+
+impl fmt::Display for CampaignId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The status of a single device's installation attempt, modeled on the
+/// update-report concept from SOTA clients: each device reports an
+/// operation result with a status and an optional message.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceResultStatus {
+    Queued,
+    Accepted,
+    Installing,
+    Succeeded,
+    Failed,
+}
+
+/// One device's reported operation result for a campaign.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DeviceResult {
+    pub device: String,
+    pub status: DeviceResultStatus,
+    pub message: Option<String>,
+}
+
+/// Aggregate installation results for a campaign, bucketed by
+/// `DeviceResultStatus`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CampaignStats {
+    pub queued: u64,
+    pub accepted: u64,
+    pub installing: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub results: Vec<DeviceResult>,
+}
+
+impl CampaignStats {
+    fn total(&self) -> u64 {
+        self.queued + self.accepted + self.installing + self.succeeded + self.failed
+    }
+
+    /// The percentage of devices that have reached a final state
+    /// (succeeded or failed/rejected).
+    pub fn percent_complete(&self) -> f64 {
+        let total = self.total();
+        if total == 0 {
+            0.0
+        } else {
+            100.0 * (self.succeeded + self.failed) as f64 / total as f64
+        }
+    }
+
+    /// Whether every device has reached a final state, so polling can stop.
+    pub fn is_terminal(&self) -> bool {
+        let total = self.total();
+        total > 0 && self.succeeded + self.failed == total
+    }
+}
+
+pub trait CampaignerApi {
+    fn list_from_flags<'a>(config: &mut Config, flags: &ArgMatches<'a>) -> Result<()>;
+    fn create_from_flags<'a>(config: &mut Config, flags: &ArgMatches<'a>) -> Result<CampaignId>;
+    fn launch_campaign(config: &mut Config, campaign: CampaignId) -> Result<()>;
+    fn cancel_campaign(config: &mut Config, campaign: CampaignId) -> Result<()>;
+    fn campaign_stats(config: &mut Config, campaign: CampaignId) -> Result<CampaignStats>;
+}
+
+#[derive(Serialize)]
+struct NewCampaign<'a> {
+    name: &'a str,
+}
+
+pub struct Campaigner;
+
+impl CampaignerApi for Campaigner {
+    fn list_from_flags<'a>(config: &mut Config, _flags: &ArgMatches<'a>) -> Result<()> {
+        let token = config.bearer_token()?;
+        let campaigns: ::serde_json::Value =
+            Client::new().get(&format!("{}/campaigns", config.campaigner_endpoint)).bearer_auth(token).send()?.error_for_status()?.json()?;
+        println!("{}", ::serde_json::to_string_pretty(&campaigns)?);
+        Ok(())
+    }
+
+    fn create_from_flags<'a>(config: &mut Config, flags: &ArgMatches<'a>) -> Result<CampaignId> {
+        let name = flags.value_of("name").expect("--name");
+        let token = config.bearer_token()?;
+        let created: CampaignId = Client::new()
+            .post(&format!("{}/campaigns", config.campaigner_endpoint))
+            .bearer_auth(token)
+            .json(&NewCampaign { name })
+            .send()?
+            .error_for_status()?
+            .json()?;
+        println!("created campaign {}", created);
+        Ok(created)
+    }
+
+    fn launch_campaign(config: &mut Config, campaign: CampaignId) -> Result<()> {
+        let token = config.bearer_token()?;
+        Client::new()
+            .post(&format!("{}/campaigns/{}/launch", config.campaigner_endpoint, campaign))
+            .bearer_auth(token)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn cancel_campaign(config: &mut Config, campaign: CampaignId) -> Result<()> {
+        let token = config.bearer_token()?;
+        Client::new()
+            .post(&format!("{}/campaigns/{}/cancel", config.campaigner_endpoint, campaign))
+            .bearer_auth(token)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn campaign_stats(config: &mut Config, campaign: CampaignId) -> Result<CampaignStats> {
+        let token = config.bearer_token()?;
+        let stats = Client::new()
+            .get(&format!("{}/campaigns/{}/stats", config.campaigner_endpoint, campaign))
+            .bearer_auth(token)
+            .send()?
+            .error_for_status()?
+            .json()?;
+        Ok(stats)
+    }
+}
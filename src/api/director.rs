@@ -0,0 +1,86 @@
+use std::fmt;
+use std::fs::File;
+use std::str::FromStr;
+
+use reqwest::Client;
+
+use auth::Authenticate;
+use config::Config;
+use error::{Error, Result};
+use registry::DeviceId;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UpdateId(pub i64);
+
+impl FromStr for UpdateId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.parse().map(UpdateId).map_err(|_| Error::Command(format!("invalid update id: {}", s)))
+    }
+}
+
+impl fmt::Display for UpdateId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The target names and versions read from a `--targets` file, before
+/// they're turned into a director-ready multi-target update.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TargetRequests {
+    pub targets: Vec<String>,
+}
+
+impl TargetRequests {
+    pub fn from_file(path: &str) -> Result<TargetRequests> {
+        let file = File::open(path)?;
+        Ok(::serde_json::from_reader(file)?)
+    }
+}
+
+/// The multi-target update body the director accepts, built from a
+/// `TargetRequests`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TufUpdates {
+    pub targets: Vec<String>,
+}
+
+impl From<TargetRequests> for TufUpdates {
+    fn from(requests: TargetRequests) -> Self {
+        TufUpdates { targets: requests.targets }
+    }
+}
+
+pub trait DirectorApi {
+    fn create_mtu(config: &mut Config, updates: &TufUpdates) -> Result<UpdateId>;
+    fn launch_mtu(config: &mut Config, update: UpdateId, device: DeviceId) -> Result<()>;
+}
+
+pub struct Director;
+
+impl DirectorApi for Director {
+    fn create_mtu(config: &mut Config, updates: &TufUpdates) -> Result<UpdateId> {
+        let token = config.bearer_token()?;
+        let created: UpdateId = Client::new()
+            .post(&format!("{}/multi_target_updates", config.director_endpoint))
+            .bearer_auth(token)
+            .json(updates)
+            .send()?
+            .error_for_status()?
+            .json()?;
+        println!("created update {}", created);
+        Ok(created)
+    }
+
+    fn launch_mtu(config: &mut Config, update: UpdateId, device: DeviceId) -> Result<()> {
+        let token = config.bearer_token()?;
+        Client::new()
+            .put(&format!("{}/admin/devices/{}/multi_target_update/{}", config.director_endpoint, device, update))
+            .bearer_auth(token)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+pub mod campaigner;
+pub mod director;
+pub mod registry;
+pub mod reposerver;
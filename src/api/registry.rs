@@ -0,0 +1,165 @@
+use std::fmt;
+use std::str::FromStr;
+
+use clap::ArgMatches;
+use reqwest::Client;
+
+use auth::Authenticate;
+use config::Config;
+use error::{Error, Result};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DeviceId(pub i64);
+
+impl FromStr for DeviceId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.parse().map(DeviceId).map_err(|_| Error::Command(format!("invalid device id: {}", s)))
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GroupId(pub i64);
+
+impl FromStr for GroupId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.parse().map(GroupId).map_err(|_| Error::Command(format!("invalid group id: {}", s)))
+    }
+}
+
+impl fmt::Display for GroupId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The kind of device being registered, e.g. a vehicle ECU vs. a generic
+/// test device.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceType {
+    Vehicle,
+    Other,
+}
+
+impl DeviceType {
+    pub fn from_flags<'a>(flags: &ArgMatches<'a>) -> Result<DeviceType> {
+        match flags.value_of("device-type").unwrap_or("vehicle").to_lowercase().as_ref() {
+            "vehicle" => Ok(DeviceType::Vehicle),
+            "other" => Ok(DeviceType::Other),
+            other => Err(Error::Command(format!("unknown device type: {}", other))),
+        }
+    }
+}
+
+pub trait RegistryApi {
+    fn list_device_flags<'a>(config: &mut Config, flags: &ArgMatches<'a>) -> Result<()>;
+    fn create_device(config: &mut Config, name: &str, id: &str, device_type: DeviceType) -> Result<()>;
+    fn delete_device(config: &mut Config, device: DeviceId) -> Result<()>;
+    fn list_group_flags<'a>(config: &mut Config, flags: &ArgMatches<'a>) -> Result<()>;
+    fn create_group(config: &mut Config, name: &str) -> Result<GroupId>;
+    fn add_to_group(config: &mut Config, group: GroupId, device: DeviceId) -> Result<()>;
+    fn remove_from_group(config: &mut Config, group: GroupId, device: DeviceId) -> Result<()>;
+    fn rename_group(config: &mut Config, group: GroupId, name: &str) -> Result<()>;
+}
+
+#[derive(Serialize)]
+struct NewDevice<'a> {
+    device_name: &'a str,
+    device_id: &'a str,
+    device_type: DeviceType,
+}
+
+#[derive(Serialize)]
+struct NewGroup<'a> {
+    group_name: &'a str,
+}
+
+pub struct Registry;
+
+impl RegistryApi for Registry {
+    fn list_device_flags<'a>(config: &mut Config, _flags: &ArgMatches<'a>) -> Result<()> {
+        let token = config.bearer_token()?;
+        let devices: ::serde_json::Value =
+            Client::new().get(&format!("{}/devices", config.registry_endpoint)).bearer_auth(token).send()?.error_for_status()?.json()?;
+        println!("{}", ::serde_json::to_string_pretty(&devices)?);
+        Ok(())
+    }
+
+    fn create_device(config: &mut Config, name: &str, id: &str, device_type: DeviceType) -> Result<()> {
+        let token = config.bearer_token()?;
+        Client::new()
+            .post(&format!("{}/devices", config.registry_endpoint))
+            .bearer_auth(token)
+            .json(&NewDevice { device_name: name, device_id: id, device_type })
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn delete_device(config: &mut Config, device: DeviceId) -> Result<()> {
+        let token = config.bearer_token()?;
+        Client::new().delete(&format!("{}/devices/{}", config.registry_endpoint, device)).bearer_auth(token).send()?.error_for_status()?;
+        Ok(())
+    }
+
+    fn list_group_flags<'a>(config: &mut Config, _flags: &ArgMatches<'a>) -> Result<()> {
+        let token = config.bearer_token()?;
+        let groups: ::serde_json::Value =
+            Client::new().get(&format!("{}/groups", config.registry_endpoint)).bearer_auth(token).send()?.error_for_status()?.json()?;
+        println!("{}", ::serde_json::to_string_pretty(&groups)?);
+        Ok(())
+    }
+
+    fn create_group(config: &mut Config, name: &str) -> Result<GroupId> {
+        let token = config.bearer_token()?;
+        let created: GroupId = Client::new()
+            .post(&format!("{}/groups", config.registry_endpoint))
+            .bearer_auth(token)
+            .json(&NewGroup { group_name: name })
+            .send()?
+            .error_for_status()?
+            .json()?;
+        println!("created group {}", created);
+        Ok(created)
+    }
+
+    fn add_to_group(config: &mut Config, group: GroupId, device: DeviceId) -> Result<()> {
+        let token = config.bearer_token()?;
+        Client::new()
+            .post(&format!("{}/groups/{}/devices/{}", config.registry_endpoint, group, device))
+            .bearer_auth(token)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn remove_from_group(config: &mut Config, group: GroupId, device: DeviceId) -> Result<()> {
+        let token = config.bearer_token()?;
+        Client::new()
+            .delete(&format!("{}/groups/{}/devices/{}", config.registry_endpoint, group, device))
+            .bearer_auth(token)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn rename_group(config: &mut Config, group: GroupId, name: &str) -> Result<()> {
+        let token = config.bearer_token()?;
+        Client::new()
+            .put(&format!("{}/groups/{}", config.registry_endpoint, group))
+            .bearer_auth(token)
+            .json(&NewGroup { group_name: name })
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use std::fs::File;
+use std::io;
+
+use clap::ArgMatches;
+use reqwest::Client;
+
+use auth::Authenticate;
+use config::Config;
+use error::{Error, Result};
+
+/// A package to upload to the reposerver, built from `--name`/`--version`/
+/// `--path`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TargetPackage {
+    pub name: String,
+    pub version: String,
+    pub path: String,
+}
+
+impl TargetPackage {
+    pub fn from_flags<'a>(flags: &ArgMatches<'a>) -> Result<TargetPackage> {
+        Ok(TargetPackage {
+            name: flags.value_of("name").expect("--name").into(),
+            version: flags.value_of("version").expect("--version").into(),
+            path: flags.value_of("path").expect("--path").into(),
+        })
+    }
+}
+
+/// A package as reported by the reposerver's target listing.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub hash: String,
+    pub size: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PackageFormat {
+    Table,
+    Json,
+}
+
+impl Default for PackageFormat {
+    fn default() -> Self {
+        PackageFormat::Table
+    }
+}
+
+/// Server-side filtering and pagination for `package list`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PackageFilter {
+    pub name: Option<String>,
+    pub format: PackageFormat,
+    pub limit: u64,
+    pub offset: u64,
+}
+
+impl PackageFilter {
+    pub fn from_flags<'a>(flags: &ArgMatches<'a>) -> Result<PackageFilter> {
+        let format = match flags.value_of("format").unwrap_or("table").to_lowercase().as_ref() {
+            "table" => PackageFormat::Table,
+            "json" => PackageFormat::Json,
+            other => return Err(Error::Command(format!("unknown --format: {}", other))),
+        };
+        let limit = flags.value_of("limit").unwrap_or("100").parse().map_err(|_| Error::Command("--limit must be a number".into()))?;
+        let offset = flags.value_of("offset").unwrap_or("0").parse().map_err(|_| Error::Command("--offset must be a number".into()))?;
+
+        Ok(PackageFilter { name: flags.value_of("name").map(String::from), format, limit, offset })
+    }
+}
+
+pub trait ReposerverApi {
+    fn add_package(config: &mut Config, package: TargetPackage) -> Result<()>;
+    fn get_package(config: &mut Config, name: &str, version: &str) -> Result<()>;
+    fn list_packages(config: &mut Config, filter: PackageFilter) -> Result<()>;
+}
+
+pub struct Reposerver;
+
+impl ReposerverApi for Reposerver {
+    fn add_package(config: &mut Config, package: TargetPackage) -> Result<()> {
+        let token = config.bearer_token()?;
+        let file = File::open(&package.path)?;
+        Client::new()
+            .put(&format!("{}/targets/{}/{}", config.reposerver_endpoint, package.name, package.version))
+            .bearer_auth(token)
+            .body(file)
+            .send()?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn get_package(config: &mut Config, name: &str, version: &str) -> Result<()> {
+        let token = config.bearer_token()?;
+        let mut response = Client::new()
+            .get(&format!("{}/targets/{}/{}", config.reposerver_endpoint, name, version))
+            .bearer_auth(token)
+            .send()?
+            .error_for_status()?;
+        let mut file = File::create(format!("{}-{}", name, version))?;
+        io::copy(&mut response, &mut file)?;
+        Ok(())
+    }
+
+    /// Fetches the known packages from the reposerver's target listing,
+    /// applying `--name` as a server-side substring filter and
+    /// `--limit`/`--offset` as pagination, then renders them as a table or
+    /// as JSON depending on `--format`.
+    fn list_packages(config: &mut Config, filter: PackageFilter) -> Result<()> {
+        let token = config.bearer_token()?;
+        let mut request = Client::new().get(&format!("{}/targets", config.reposerver_endpoint)).bearer_auth(token);
+        if let Some(ref name) = filter.name {
+            request = request.query(&[("name", name.as_str())]);
+        }
+        request = request.query(&[("limit", filter.limit.to_string()), ("offset", filter.offset.to_string())]);
+
+        let packages: Vec<Package> = request.send()?.error_for_status()?.json()?;
+
+        match filter.format {
+            PackageFormat::Json => println!("{}", ::serde_json::to_string_pretty(&packages)?),
+            PackageFormat::Table => {
+                println!("{:<30} {:<15} {:<10} {}", "NAME", "VERSION", "SIZE", "HASH");
+                for package in &packages {
+                    println!("{:<30} {:<15} {:<10} {}", package.name, package.version, package.size, package.hash);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use config::Config;
+use error::{Error, Result};
+
+/// Refresh a cached token this many seconds before it actually expires, so a
+/// nearly-stale token is never handed to an in-flight request.
+const EXPIRY_SKEW_SECS: u64 = 30;
+
+#[derive(Deserialize, Clone, Debug)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// A cached OAuth2 bearer token and the instant it stops being usable.
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub access_token: String,
+    expires_at: u64,
+}
+
+impl Token {
+    fn is_expired(&self) -> bool {
+        now_secs() + EXPIRY_SKEW_SECS >= self.expires_at
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).expect("system clock before epoch").as_secs()
+}
+
+/// Fetches and caches OAuth2 bearer tokens via the client-credentials grant,
+/// so API modules can attach `Authorization: Bearer <token>` without each one
+/// re-implementing the dance.
+pub trait Authenticate {
+    /// Returns a cached bearer token, fetching or refreshing it first if
+    /// necessary.
+    fn bearer_token(&mut self) -> Result<String>;
+
+    /// Drops the cached token, forcing the next `bearer_token` call to fetch
+    /// a fresh one. Call this after a request comes back `401 Unauthorized`.
+    fn invalidate_token(&mut self);
+}
+
+impl Authenticate for Config {
+    fn bearer_token(&mut self) -> Result<String> {
+        let needs_refresh = match self.token {
+            Some(ref token) => token.is_expired(),
+            None => true,
+        };
+        if needs_refresh {
+            self.token = Some(fetch_token(self)?);
+        }
+        Ok(self.token.as_ref().expect("token set above").access_token.clone())
+    }
+
+    fn invalidate_token(&mut self) {
+        self.token = None;
+    }
+}
+
+fn fetch_token(config: &Config) -> Result<Token> {
+    let resp: TokenResponse = Client::new()
+        .post(&config.token_endpoint)
+        .basic_auth(&config.client_id, Some(&config.client_secret))
+        .form(&[("grant_type", "client_credentials")])
+        .send()?
+        .error_for_status()
+        .map_err(|err| Error::Command(format!("token request failed: {}", err)))?
+        .json()?;
+
+    Ok(Token { access_token: resp.access_token, expires_at: now_secs() + resp.expires_in })
+}
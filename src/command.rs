@@ -1,19 +1,29 @@
-use clap::ArgMatches;
+use clap::{App, AppSettings, Arg, ArgMatches, Shell, SubCommand};
+use std::io;
 use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
 
 use api::{
-    campaigner::{Campaigner, CampaignerApi},
-    director::{Director, DirectorApi, TargetRequests, TufUpdates},
-    registry::{DeviceType, Registry, RegistryApi},
-    reposerver::{Reposerver, ReposerverApi, TargetPackage},
+    campaigner::{Campaigner, CampaignerApi, CampaignId, CampaignStats, DeviceResultStatus},
+    director::{Director, DirectorApi, TargetRequests, TufUpdates, UpdateId},
+    registry::{DeviceType, GroupId, Registry, RegistryApi},
+    reposerver::{PackageFilter, Reposerver, ReposerverApi, TargetPackage},
 };
 use config::Config;
 use error::{Error, Result};
+use events::Gateway;
+use script::Script;
 
 
 /// Execute a command.
+///
+/// Returns the primary identifier the command produced (e.g. a freshly
+/// created campaign or group id), or `None` if it doesn't produce one or
+/// the underlying API doesn't yet surface it. `Command::Run` uses this to
+/// let later steps in a script reference an earlier step's output.
 pub trait Exec<'a> {
-    fn exec(&self, flags: &ArgMatches<'a>) -> Result<()>;
+    fn exec(&self, flags: &ArgMatches<'a>) -> Result<Option<String>>;
 }
 
 
@@ -26,19 +36,25 @@ pub enum Command {
     Group,
     Package,
     Update,
+    Completions,
+    Run,
+    Watch,
 }
 
 impl<'a> Exec<'a> for Command {
-    fn exec(&self, flags: &ArgMatches<'a>) -> Result<()> {
+    fn exec(&self, flags: &ArgMatches<'a>) -> Result<Option<String>> {
         let (cmd, args) = flags.subcommand();
-        #[cfg_attr(rustfmt, rustfmt_skip)] 
+        #[cfg_attr(rustfmt, rustfmt_skip)]
         match self {
-            Command::Init     => Config::init_from_flags(flags),
-            Command::Campaign => cmd.parse::<Campaign>()?.exec(args.expect("campaign args")),
-            Command::Device   => cmd.parse::<Device>()?.exec(args.expect("device args")),
-            Command::Group    => cmd.parse::<Group>()?.exec(args.expect("group args")),
-            Command::Package  => cmd.parse::<Package>()?.exec(args.expect("package args")),
-            Command::Update   => cmd.parse::<Update>()?.exec(args.expect("update args")),
+            Command::Init        => Config::init_from_flags(flags).map(|_| None),
+            Command::Campaign    => cmd.parse::<Campaign>()?.exec(args.expect("campaign args")),
+            Command::Device      => cmd.parse::<Device>()?.exec(args.expect("device args")),
+            Command::Group       => cmd.parse::<Group>()?.exec(args.expect("group args")),
+            Command::Package     => cmd.parse::<Package>()?.exec(args.expect("package args")),
+            Command::Update      => cmd.parse::<Update>()?.exec(args.expect("update args")),
+            Command::Completions => Self::gen_completions(args.expect("completions args")).map(|_| None),
+            Command::Run         => Self::run_script(args.expect("run args")).map(|_| None),
+            Command::Watch       => Self::watch(args.expect("watch args")).map(|_| None),
         }
     }
 }
@@ -47,19 +63,175 @@ impl FromStr for Command {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        #[cfg_attr(rustfmt, rustfmt_skip)] 
+        #[cfg_attr(rustfmt, rustfmt_skip)]
         match s.to_lowercase().as_ref() {
-            "init"     => Ok(Command::Init),
-            "campaign" => Ok(Command::Campaign),
-            "device"   => Ok(Command::Device),
-            "group"    => Ok(Command::Group),
-            "package"  => Ok(Command::Package),
-            "update"   => Ok(Command::Update),
+            "init"        => Ok(Command::Init),
+            "campaign"    => Ok(Command::Campaign),
+            "device"      => Ok(Command::Device),
+            "group"       => Ok(Command::Group),
+            "package"     => Ok(Command::Package),
+            "update"      => Ok(Command::Update),
+            "completions" => Ok(Command::Completions),
+            "run"         => Ok(Command::Run),
+            "watch"       => Ok(Command::Watch),
             _ => Err(Error::Command(format!("unknown command: {}", s))),
         }
     }
 }
 
+impl Command {
+    /// Builds the `clap::App` that defines the full `campaign-manager`
+    /// argument tree. Shared by `main` (to parse `std::env::args`) and by
+    /// `completions` (to generate shell completion scripts from the same
+    /// definition, rather than keeping a second copy of the command tree).
+    pub fn build_cli<'b, 'c>() -> App<'b, 'c> {
+        App::new("campaign-manager")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                SubCommand::with_name("init")
+                    .arg(Arg::with_name("token-endpoint").long("token-endpoint").takes_value(true))
+                    .arg(Arg::with_name("client-id").long("client-id").takes_value(true))
+                    .arg(Arg::with_name("client-secret").long("client-secret").takes_value(true)),
+            )
+            .subcommand(
+                SubCommand::with_name("campaign")
+                    .setting(AppSettings::SubcommandRequiredElseHelp)
+                    .subcommand(SubCommand::with_name("list"))
+                    .subcommand(SubCommand::with_name("create").arg(Arg::with_name("name").long("name").takes_value(true).required(true)))
+                    .subcommand(SubCommand::with_name("launch").arg(Arg::with_name("campaign").long("campaign").takes_value(true).required(true)))
+                    .subcommand(SubCommand::with_name("cancel").arg(Arg::with_name("campaign").long("campaign").takes_value(true).required(true)))
+                    .subcommand(
+                        SubCommand::with_name("status")
+                            .arg(Arg::with_name("campaign").long("campaign").takes_value(true).required(true))
+                            .arg(Arg::with_name("watch").long("watch"))
+                            .arg(Arg::with_name("interval").long("interval").takes_value(true))
+                            .arg(Arg::with_name("failed-only").long("failed-only")),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("device")
+                    .setting(AppSettings::SubcommandRequiredElseHelp)
+                    .subcommand(SubCommand::with_name("list"))
+                    .subcommand(
+                        SubCommand::with_name("create")
+                            .arg(Arg::with_name("name").long("name").takes_value(true).required(true))
+                            .arg(Arg::with_name("id").long("id").takes_value(true).required(true))
+                            .arg(Arg::with_name("device-type").long("device-type").takes_value(true)),
+                    )
+                    .subcommand(SubCommand::with_name("delete").arg(Arg::with_name("device").long("device").takes_value(true).required(true))),
+            )
+            .subcommand(
+                SubCommand::with_name("group")
+                    .setting(AppSettings::SubcommandRequiredElseHelp)
+                    .subcommand(SubCommand::with_name("list"))
+                    .subcommand(SubCommand::with_name("create").arg(Arg::with_name("name").long("name").takes_value(true).required(true)))
+                    .subcommand(
+                        SubCommand::with_name("add")
+                            .arg(Arg::with_name("group").long("group").takes_value(true).required(true))
+                            .arg(Arg::with_name("device").long("device").takes_value(true).required(true)),
+                    )
+                    .subcommand(
+                        SubCommand::with_name("rename")
+                            .arg(Arg::with_name("group").long("group").takes_value(true).required(true))
+                            .arg(Arg::with_name("name").long("name").takes_value(true).required(true)),
+                    )
+                    .subcommand(
+                        SubCommand::with_name("remove")
+                            .arg(Arg::with_name("group").long("group").takes_value(true).required(true))
+                            .arg(Arg::with_name("device").long("device").takes_value(true).required(true)),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("package")
+                    .setting(AppSettings::SubcommandRequiredElseHelp)
+                    .subcommand(
+                        SubCommand::with_name("list")
+                            .arg(Arg::with_name("name").long("name").takes_value(true))
+                            .arg(Arg::with_name("format").long("format").takes_value(true))
+                            .arg(Arg::with_name("limit").long("limit").takes_value(true))
+                            .arg(Arg::with_name("offset").long("offset").takes_value(true)),
+                    )
+                    .subcommand(
+                        SubCommand::with_name("add")
+                            .arg(Arg::with_name("name").long("name").takes_value(true).required(true))
+                            .arg(Arg::with_name("version").long("version").takes_value(true).required(true))
+                            .arg(Arg::with_name("path").long("path").takes_value(true).required(true)),
+                    )
+                    .subcommand(
+                        SubCommand::with_name("fetch")
+                            .arg(Arg::with_name("name").long("name").takes_value(true).required(true))
+                            .arg(Arg::with_name("version").long("version").takes_value(true).required(true)),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("update")
+                    .setting(AppSettings::SubcommandRequiredElseHelp)
+                    .subcommand(SubCommand::with_name("create").arg(Arg::with_name("targets").long("targets").takes_value(true).required(true)))
+                    .subcommand(
+                        SubCommand::with_name("launch")
+                            .arg(Arg::with_name("update").long("update").takes_value(true).required(true))
+                            .arg(Arg::with_name("device").long("device").takes_value(true).required(true)),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("completions")
+                    .arg(Arg::with_name("shell").long("shell").takes_value(true).required(true)),
+            )
+            .subcommand(
+                SubCommand::with_name("run")
+                    .arg(Arg::with_name("file").long("file").takes_value(true).required(true))
+                    .arg(Arg::with_name("continue-on-error").long("continue-on-error")),
+            )
+            .subcommand(
+                SubCommand::with_name("watch")
+                    .arg(Arg::with_name("campaign").long("campaign").takes_value(true))
+                    .arg(Arg::with_name("device").long("device").takes_value(true))
+                    .arg(Arg::with_name("json").long("json")),
+            )
+    }
+
+    /// Generates a shell completion script for `--shell` and writes it to
+    /// stdout, using the exact `App` definition that parses real invocations.
+    fn gen_completions<'a>(flags: &ArgMatches<'a>) -> Result<()> {
+        let shell = flags.value_of("shell").expect("--shell");
+        let shell = shell
+            .parse::<Shell>()
+            .map_err(|_| Error::Command(format!("unsupported shell: {}", shell)))?;
+        Self::build_cli().gen_completions_to("campaign-manager", shell, &mut io::stdout());
+        Ok(())
+    }
+
+    /// Runs an ordered list of steps read from a YAML or JSON `--file`,
+    /// dispatching each through this same `Command`/`Exec` tree, and prints
+    /// a structured report of how each step went.
+    fn run_script<'a>(flags: &ArgMatches<'a>) -> Result<()> {
+        let path = flags.value_of("file").expect("--file");
+        let continue_on_error = flags.is_present("continue-on-error");
+        let report = Script::load(path)?.run(continue_on_error)?;
+        println!("{}", ::serde_json::to_string_pretty(&report)?);
+        Ok(())
+    }
+
+    /// Streams campaign and device-update events as they arrive, optionally
+    /// filtered to a single `--campaign` or `--device`, reconnecting with
+    /// backoff if the stream drops.
+    fn watch<'a>(flags: &ArgMatches<'a>) -> Result<()> {
+        let mut config = Config::load_default()?;
+        let campaign = flags.value_of("campaign").map(String::from);
+        let device = flags.value_of("device").map(String::from);
+        let json = flags.is_present("json");
+
+        Gateway::connect(&mut config)?.watch(campaign.as_ref().map(String::as_str), device.as_ref().map(String::as_str), |event| {
+            if json {
+                println!("{}", ::serde_json::to_string(&event)?);
+            } else {
+                println!("{}", event);
+            }
+            Ok(())
+        })
+    }
+}
+
 
 /// Available campaign sub-commands.
 #[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
@@ -68,33 +240,74 @@ pub enum Campaign {
     Create,
     Launch,
     Cancel,
+    Status,
 }
 
 impl<'a> Exec<'a> for Campaign {
-    fn exec(&self, flags: &ArgMatches<'a>) -> Result<()> {
+    fn exec(&self, flags: &ArgMatches<'a>) -> Result<Option<String>> {
         let mut config = Config::load_default()?;
         let campaign = || flags.value_of("campaign").expect("--campaign").parse();
 
-        #[cfg_attr(rustfmt, rustfmt_skip)] 
+        #[cfg_attr(rustfmt, rustfmt_skip)]
         match self {
-            Campaign::List   => Campaigner::list_from_flags(&mut config, flags),
-            Campaign::Create => Campaigner::create_from_flags(&mut config, flags),
-            Campaign::Launch => Campaigner::launch_campaign(&mut config, campaign()?),
-            Campaign::Cancel => Campaigner::cancel_campaign(&mut config, campaign()?),
+            Campaign::List   => Campaigner::list_from_flags(&mut config, flags).map(|_| None),
+            Campaign::Create => Campaigner::create_from_flags(&mut config, flags).map(|id: CampaignId| Some(id.to_string())),
+            Campaign::Launch => Campaigner::launch_campaign(&mut config, campaign()?).map(|_| None),
+            Campaign::Cancel => Campaigner::cancel_campaign(&mut config, campaign()?).map(|_| None),
+            Campaign::Status => Self::status(&mut config, campaign()?, flags).map(|_| None),
         }
     }
 }
 
+impl Campaign {
+    /// Poll and report per-device rollout progress for a campaign, optionally
+    /// re-polling every `--interval` seconds until a terminal state is reached.
+    fn status<'a>(config: &mut Config, campaign_id: CampaignId, flags: &ArgMatches<'a>) -> Result<()> {
+        let watch = flags.is_present("watch");
+        let interval = flags
+            .value_of("interval")
+            .unwrap_or("10")
+            .parse()
+            .map_err(|_| Error::Command("--interval must be a number of seconds".into()))?;
+        let failed_only = flags.is_present("failed-only");
+
+        loop {
+            let stats = Campaigner::campaign_stats(config, campaign_id)?;
+            Self::print_stats(&stats, failed_only);
+            if !watch || stats.is_terminal() {
+                break;
+            }
+            thread::sleep(Duration::from_secs(interval));
+        }
+        Ok(())
+    }
+
+    fn print_stats(stats: &CampaignStats, failed_only: bool) {
+        if failed_only {
+            for failure in stats.results.iter().filter(|result| result.status == DeviceResultStatus::Failed) {
+                println!("{}: {}", failure.device, failure.message.as_ref().map(String::as_str).unwrap_or("unknown error"));
+            }
+            return;
+        }
+
+        println!(
+            "queued: {}  accepted: {}  installing: {}  succeeded: {}  failed: {}  ({:.1}% complete)",
+            stats.queued, stats.accepted, stats.installing, stats.succeeded, stats.failed, stats.percent_complete()
+        );
+    }
+}
+
 impl FromStr for Campaign {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        #[cfg_attr(rustfmt, rustfmt_skip)] 
+        #[cfg_attr(rustfmt, rustfmt_skip)]
         match s.to_lowercase().as_ref() {
             "list"   => Ok(Campaign::List),
             "create" => Ok(Campaign::Create),
             "launch" => Ok(Campaign::Launch),
             "cancel" => Ok(Campaign::Cancel),
+            "status" => Ok(Campaign::Status),
             _ => Err(Error::Command(format!("unknown campaign subcommand: {}", s))),
         }
     }
@@ -110,17 +323,17 @@ pub enum Device {
 }
 
 impl<'a> Exec<'a> for Device {
-    fn exec(&self, flags: &ArgMatches<'a>) -> Result<()> {
+    fn exec(&self, flags: &ArgMatches<'a>) -> Result<Option<String>> {
         let mut config = Config::load_default()?;
         let device = || flags.value_of("device").expect("--device").parse();
         let name = || flags.value_of("name").expect("--name");
         let id = || flags.value_of("id").expect("--id");
 
-        #[cfg_attr(rustfmt, rustfmt_skip)] 
+        #[cfg_attr(rustfmt, rustfmt_skip)]
         match self {
-            Device::List   => Registry::list_device_flags(&mut config, flags),
-            Device::Create => Registry::create_device(&mut config, name(), id(), DeviceType::from_flags(flags)?),
-            Device::Delete => Registry::delete_device(&mut config, device()?),
+            Device::List   => Registry::list_device_flags(&mut config, flags).map(|_| None),
+            Device::Create => Registry::create_device(&mut config, name(), id(), DeviceType::from_flags(flags)?).map(|_| Some(id().into())),
+            Device::Delete => Registry::delete_device(&mut config, device()?).map(|_| None),
         }
     }
 }
@@ -151,19 +364,19 @@ pub enum Group {
 }
 
 impl<'a> Exec<'a> for Group {
-    fn exec(&self, flags: &ArgMatches<'a>) -> Result<()> {
+    fn exec(&self, flags: &ArgMatches<'a>) -> Result<Option<String>> {
         let mut config = Config::load_default()?;
         let group = || flags.value_of("group").expect("--group").parse();
         let device = || flags.value_of("device").expect("--device").parse();
         let name = || flags.value_of("name").expect("--name");
 
-        #[cfg_attr(rustfmt, rustfmt_skip)] 
+        #[cfg_attr(rustfmt, rustfmt_skip)]
         match self {
-            Group::List   => Registry::list_group_flags(&mut config, flags),
-            Group::Create => Registry::create_group(&mut config, name()),
-            Group::Add    => Registry::add_to_group(&mut config, group()?, device()?),
-            Group::Remove => Registry::remove_from_group(&mut config, group()?, device()?),
-            Group::Rename => Registry::rename_group(&mut config, group()?, name()),
+            Group::List   => Registry::list_group_flags(&mut config, flags).map(|_| None),
+            Group::Create => Registry::create_group(&mut config, name()).map(|id: GroupId| Some(id.to_string())),
+            Group::Add    => Registry::add_to_group(&mut config, group()?, device()?).map(|_| None),
+            Group::Remove => Registry::remove_from_group(&mut config, group()?, device()?).map(|_| None),
+            Group::Rename => Registry::rename_group(&mut config, group()?, name()).map(|_| None),
         }
     }
 }
@@ -194,16 +407,16 @@ pub enum Package {
 }
 
 impl<'a> Exec<'a> for Package {
-    fn exec(&self, flags: &ArgMatches<'a>) -> Result<()> {
+    fn exec(&self, flags: &ArgMatches<'a>) -> Result<Option<String>> {
         let mut config = Config::load_default()?;
         let name = || flags.value_of("name").expect("--name");
         let version = || flags.value_of("version").expect("--version");
 
-        #[cfg_attr(rustfmt, rustfmt_skip)] 
+        #[cfg_attr(rustfmt, rustfmt_skip)]
         match self {
-            Package::List  => panic!("API not yet supported"),
-            Package::Add   => Reposerver::add_package(&mut config, TargetPackage::from_flags(flags)?),
-            Package::Fetch => Reposerver::get_package(&mut config, name(), version()),
+            Package::List  => Reposerver::list_packages(&mut config, PackageFilter::from_flags(flags)?).map(|_| None),
+            Package::Add   => Reposerver::add_package(&mut config, TargetPackage::from_flags(flags)?).map(|_| None),
+            Package::Fetch => Reposerver::get_package(&mut config, name(), version()).map(|_| None),
         }
     }
 }
@@ -231,15 +444,17 @@ pub enum Update {
 }
 
 impl<'a> Exec<'a> for Update {
-    fn exec(&self, flags: &ArgMatches<'a>) -> Result<()> {
+    fn exec(&self, flags: &ArgMatches<'a>) -> Result<Option<String>> {
         let mut config = Config::load_default()?;
         let update = || flags.value_of("update").expect("--update").parse();
         let device = || flags.value_of("device").expect("--device").parse();
         let targets = || flags.value_of("targets").expect("--targets");
 
         match self {
-            Update::Create => Director::create_mtu(&mut config, &TufUpdates::from(TargetRequests::from_file(targets())?)),
-            Update::Launch => Director::launch_mtu(&mut config, update()?, device()?)
+            Update::Create => {
+                Director::create_mtu(&mut config, &TufUpdates::from(TargetRequests::from_file(targets())?)).map(|id: UpdateId| Some(id.to_string()))
+            }
+            Update::Launch => Director::launch_mtu(&mut config, update()?, device()?).map(|_| None),
         }
     }
 }
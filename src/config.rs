@@ -0,0 +1,65 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+
+use auth::Token;
+use error::Result;
+
+fn config_path() -> PathBuf {
+    let home = env::var("HOME").unwrap_or_else(|_| ".".into());
+    PathBuf::from(home).join(".config").join("campaign-manager").join("config.json")
+}
+
+/// Persisted CLI configuration: the backend endpoints each API module talks
+/// to, the OAuth2 client-credentials settings used to authenticate against
+/// them, and (in memory only) the bearer token those settings produce.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Config {
+    pub campaigner_endpoint: String,
+    pub director_endpoint: String,
+    pub registry_endpoint: String,
+    pub reposerver_endpoint: String,
+    pub events_endpoint: String,
+
+    pub token_endpoint: String,
+    pub client_id: String,
+    pub client_secret: String,
+
+    #[serde(skip)]
+    pub token: Option<Token>,
+}
+
+impl Config {
+    /// Loads the config written by `campaign-manager init` from
+    /// `~/.config/campaign-manager/config.json`.
+    pub fn load_default() -> Result<Config> {
+        let bytes = fs::read(config_path())?;
+        Ok(::serde_json::from_slice(&bytes)?)
+    }
+
+    /// Writes `--token-endpoint`, `--client-id`, and `--client-secret` (and
+    /// any other already-configured fields) back to the config file,
+    /// creating it if this is the first `init`.
+    pub fn init_from_flags<'a>(flags: &ArgMatches<'a>) -> Result<()> {
+        let mut config = Config::load_default().unwrap_or_default();
+
+        if let Some(endpoint) = flags.value_of("token-endpoint") {
+            config.token_endpoint = endpoint.into();
+        }
+        if let Some(client_id) = flags.value_of("client-id") {
+            config.client_id = client_id.into();
+        }
+        if let Some(client_secret) = flags.value_of("client-secret") {
+            config.client_secret = client_secret.into();
+        }
+
+        let path = config_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, ::serde_json::to_vec_pretty(&config)?)?;
+        Ok(())
+    }
+}
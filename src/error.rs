@@ -0,0 +1,51 @@
+use std::fmt;
+use std::io;
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+/// The single error type threaded through every module via `?`.
+#[derive(Debug)]
+pub enum Error {
+    /// A CLI-level problem: bad flags, an unknown subcommand, an invalid id.
+    Command(String),
+    Io(io::Error),
+    Http(::reqwest::Error),
+    Json(::serde_json::Error),
+    Yaml(::serde_yaml::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Command(ref msg) => write!(f, "{}", msg),
+            Error::Io(ref err) => write!(f, "{}", err),
+            Error::Http(ref err) => write!(f, "{}", err),
+            Error::Json(ref err) => write!(f, "{}", err),
+            Error::Yaml(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<::reqwest::Error> for Error {
+    fn from(err: ::reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
+
+impl From<::serde_json::Error> for Error {
+    fn from(err: ::serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<::serde_yaml::Error> for Error {
+    fn from(err: ::serde_yaml::Error) -> Self {
+        Error::Yaml(err)
+    }
+}
@@ -0,0 +1,193 @@
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::thread;
+use std::time::Duration;
+
+use reqwest::{Response, StatusCode};
+
+use auth::Authenticate;
+use config::Config;
+use error::{Error, Result};
+
+/// Starting and maximum backoff between reconnect attempts when the event
+/// stream drops.
+const RECONNECT_MIN_SECS: u64 = 1;
+const RECONNECT_MAX_SECS: u64 = 30;
+
+/// A decoded campaign or device-update event, as surfaced by the backend's
+/// event stream.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "eventType")]
+pub enum Event {
+    CampaignLaunched { campaign: String },
+    CampaignCompleted { campaign: String },
+    DeviceUpdateStarted { campaign: String, device: String },
+    DeviceUpdateSucceeded { campaign: String, device: String },
+    DeviceUpdateFailed { campaign: String, device: String, reason: String },
+}
+
+impl Event {
+    fn campaign(&self) -> &str {
+        match self {
+            Event::CampaignLaunched { campaign }
+            | Event::CampaignCompleted { campaign }
+            | Event::DeviceUpdateStarted { campaign, .. }
+            | Event::DeviceUpdateSucceeded { campaign, .. }
+            | Event::DeviceUpdateFailed { campaign, .. } => campaign,
+        }
+    }
+
+    fn device(&self) -> Option<&str> {
+        match self {
+            Event::DeviceUpdateStarted { device, .. }
+            | Event::DeviceUpdateSucceeded { device, .. }
+            | Event::DeviceUpdateFailed { device, .. } => Some(device),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::CampaignLaunched { campaign } => write!(f, "campaign {} launched", campaign),
+            Event::CampaignCompleted { campaign } => write!(f, "campaign {} completed", campaign),
+            Event::DeviceUpdateStarted { campaign, device } => write!(f, "campaign {}: device {} update started", campaign, device),
+            Event::DeviceUpdateSucceeded { campaign, device } => write!(f, "campaign {}: device {} update succeeded", campaign, device),
+            Event::DeviceUpdateFailed { campaign, device, reason } => {
+                write!(f, "campaign {}: device {} update failed: {}", campaign, device, reason)
+            }
+        }
+    }
+}
+
+/// Distinguishes a dropped/malformed connection (retryable, with backoff)
+/// from a failure raised by the caller's `on_event` callback (terminal:
+/// retrying would just invoke the same callback again).
+enum StreamError {
+    Transport(Error),
+    Callback(Error),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Transport(err) | StreamError::Callback(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/// Establishes the long-lived connection to the backend's event stream and
+/// hands decoded `Event`s to an interpreter loop, reconnecting with
+/// exponential backoff if the connection drops.
+///
+/// The stream is chunked HTTP: the backend keeps the response open and
+/// writes one JSON-encoded `Event` per line as they occur, so reading the
+/// next event is a plain blocking `BufRead::read_line` over the open
+/// connection rather than a poll.
+pub struct Gateway {
+    config: Config,
+    reader: Option<BufReader<Response>>,
+}
+
+impl Gateway {
+    pub fn connect(config: &mut Config) -> Result<Gateway> {
+        Ok(Gateway { config: config.clone(), reader: None })
+    }
+
+    /// Reads events from the stream until it ends, calling `on_event` for
+    /// each one that passes the `campaign`/`device` filters, then
+    /// reconnects with backoff and resumes. Transport failures (the
+    /// connection dropping, a malformed line) are logged and retried;
+    /// a failure from `on_event` itself is terminal and is returned to
+    /// the caller immediately, since retrying would just call it again
+    /// with the same or a later event.
+    pub fn watch<F>(&mut self, campaign: Option<&str>, device: Option<&str>, mut on_event: F) -> Result<()>
+    where
+        F: FnMut(Event) -> Result<()>,
+    {
+        let mut backoff = RECONNECT_MIN_SECS;
+
+        loop {
+            match self.read_stream(campaign, device, &mut on_event) {
+                Ok(()) => {
+                    self.reader = None;
+                    thread::sleep(Duration::from_secs(RECONNECT_MIN_SECS));
+                    backoff = RECONNECT_MIN_SECS;
+                }
+                Err(StreamError::Callback(err)) => return Err(err),
+                Err(StreamError::Transport(err)) => {
+                    self.reader = None;
+                    eprintln!("event stream dropped: {}; reconnecting in {}s", err, backoff);
+                    thread::sleep(Duration::from_secs(backoff));
+                    backoff = (backoff * 2).min(RECONNECT_MAX_SECS);
+                }
+            }
+        }
+    }
+
+    /// Reads events one at a time off the open connection (opening it first
+    /// if needed) until the stream ends, dispatching each to `on_event`.
+    /// The underlying `read_line` blocks until the backend writes the next
+    /// event, so this never busy-loops waiting for data.
+    fn read_stream<F>(&mut self, campaign: Option<&str>, device: Option<&str>, on_event: &mut F) -> ::std::result::Result<(), StreamError>
+    where
+        F: FnMut(Event) -> Result<()>,
+    {
+        loop {
+            let event = match self.next_event().map_err(StreamError::Transport)? {
+                Some(event) => event,
+                None => return Ok(()),
+            };
+            if campaign.map(|id| id != event.campaign()).unwrap_or(false) {
+                continue;
+            }
+            if device.map(|id| event.device() != Some(id)).unwrap_or(false) {
+                continue;
+            }
+            on_event(event).map_err(StreamError::Callback)?;
+        }
+    }
+
+    /// Returns the next event off the stream, opening the connection first
+    /// if it isn't already established. Returns `Ok(None)` once the
+    /// backend closes the stream cleanly.
+    fn next_event(&mut self) -> Result<Option<Event>> {
+        if self.reader.is_none() {
+            self.reader = Some(self.open_stream()?);
+        }
+        let reader = self.reader.as_mut().expect("reader opened above");
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                return Ok(None);
+            }
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            return Ok(Some(::serde_json::from_str(line)?));
+        }
+    }
+
+    fn open_stream(&mut self) -> Result<BufReader<Response>> {
+        let token = self.config.bearer_token()?;
+        let response = ::reqwest::Client::new().get(&self.config.events_endpoint).bearer_auth(&token).send()?;
+
+        if response.status() == StatusCode::Unauthorized {
+            self.config.invalidate_token();
+            let token = self.config.bearer_token()?;
+            let response = ::reqwest::Client::new().get(&self.config.events_endpoint).bearer_auth(&token).send()?;
+            return Self::into_reader(response);
+        }
+        Self::into_reader(response)
+    }
+
+    fn into_reader(response: Response) -> Result<BufReader<Response>> {
+        let response = response.error_for_status().map_err(|err| Error::Command(format!("event stream request failed: {}", err)))?;
+        Ok(BufReader::new(response))
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use command::{Command, Exec};
+use error::{Error, Result};
+
+/// One step of a `campaign-manager run` script, mirroring a single CLI
+/// invocation (e.g. `campaign create --name rollout-1`).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Step {
+    /// A short, unique name used to label this step in the report and as
+    /// the substitution target for `${name}` in later steps.
+    pub name: String,
+    /// The command line to run, exactly as it would be typed, except that
+    /// `${step}` is replaced with the id that `step` produced before the
+    /// line is parsed.
+    pub command: String,
+}
+
+/// The outcome of running a single `Step`.
+#[derive(Serialize, Clone, Debug)]
+pub struct StepResult {
+    pub name: String,
+    pub success: bool,
+    pub id: Option<String>,
+    pub error: Option<String>,
+}
+
+/// The full report produced by running a `Script`.
+#[derive(Serialize, Clone, Debug)]
+pub struct RunReport {
+    pub steps: Vec<StepResult>,
+}
+
+/// An ordered, reproducible sequence of `campaign-manager` invocations read
+/// from a YAML or JSON file, so a whole rollout can be driven in one call
+/// instead of many one-shot commands.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Script {
+    steps: Vec<Step>,
+}
+
+impl Script {
+    /// Reads a list of `Step`s from a YAML or JSON file, selected by the
+    /// file extension (`.json` parses as JSON, anything else as YAML).
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let steps = if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+            ::serde_json::from_reader(file)?
+        } else {
+            ::serde_yaml::from_reader(file).map_err(|err| Error::Command(format!("invalid script: {}", err)))?
+        };
+        Ok(Script { steps })
+    }
+
+    /// Runs each step in order through the existing `Command`/`Exec` tree,
+    /// substituting `${step}` references with ids captured from earlier
+    /// steps. When `continue_on_error` is false, the first failing step
+    /// stops the run; otherwise every step is attempted and the report
+    /// records each outcome.
+    pub fn run(&self, continue_on_error: bool) -> Result<RunReport> {
+        let mut vars = HashMap::new();
+        let mut results = Vec::with_capacity(self.steps.len());
+
+        for step in &self.steps {
+            let command = substitute(&step.command, &vars);
+            let result = Self::run_step(&step.name, &command);
+
+            if let Some(ref id) = result.id {
+                vars.insert(step.name.clone(), id.clone());
+            }
+            let failed = !result.success;
+            results.push(result);
+            if failed && !continue_on_error {
+                break;
+            }
+        }
+
+        Ok(RunReport { steps: results })
+    }
+
+    fn run_step(name: &str, command: &str) -> StepResult {
+        let argv = match split_command_line(command) {
+            Ok(argv) => argv,
+            Err(err) => return StepResult { name: name.into(), success: false, id: None, error: Some(err.to_string()) },
+        };
+        let argv = ["campaign-manager".to_string()].iter().cloned().chain(argv).collect::<Vec<_>>();
+
+        match Command::build_cli().get_matches_from_safe(argv) {
+            Ok(matches) => {
+                let (cmd, args) = matches.subcommand();
+                match cmd.parse::<Command>().and_then(|c| c.exec(args.expect("subcommand args"))) {
+                    Ok(id) => StepResult { name: name.into(), success: true, id, error: None },
+                    Err(err) => StepResult { name: name.into(), success: false, id: None, error: Some(err.to_string()) },
+                }
+            }
+            Err(err) => StepResult { name: name.into(), success: false, id: None, error: Some(err.to_string()) },
+        }
+    }
+}
+
+fn substitute(command: &str, vars: &HashMap<String, String>) -> String {
+    let mut result = command.to_string();
+    for (name, value) in vars {
+        result = result.replace(&format!("${{{}}}", name), value);
+    }
+    result
+}
+
+/// Splits a step's `command` line into argv the way a shell would: tokens
+/// are whitespace-separated unless wrapped in matching `'` or `"` quotes,
+/// so a flag value containing spaces (a device name, a file path) survives
+/// intact instead of being silently split apart.
+fn split_command_line(command: &str) -> Result<Vec<String>> {
+    let mut argv = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut quote = None;
+    for c in command.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                in_token = true;
+            }
+            None if c.is_whitespace() => {
+                if in_token {
+                    argv.push(current.clone());
+                    current.clear();
+                    in_token = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(Error::Command(format!("unterminated quote in step command: {}", command)));
+    }
+    if in_token {
+        argv.push(current);
+    }
+    Ok(argv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_command_line, substitute};
+    use std::collections::HashMap;
+
+    #[test]
+    fn split_command_line_keeps_quoted_spaces_together() {
+        let argv = split_command_line(r#"device create --name "my device" --id 1"#).unwrap();
+        assert_eq!(argv, vec!["device", "create", "--name", "my device", "--id", "1"]);
+    }
+
+    #[test]
+    fn split_command_line_errors_on_unterminated_quote() {
+        let err = split_command_line(r#"device create --name "my device"#).unwrap_err();
+        assert!(err.to_string().contains("unterminated quote"));
+    }
+
+    #[test]
+    fn substitute_replaces_step_reference() {
+        let mut vars = HashMap::new();
+        vars.insert("create-campaign".to_string(), "42".to_string());
+        let result = substitute("campaign launch --id ${create-campaign}", &vars);
+        assert_eq!(result, "campaign launch --id 42");
+    }
+}